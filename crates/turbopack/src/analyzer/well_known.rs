@@ -2,7 +2,9 @@ use std::{mem::take, sync::Arc};
 
 use url::Url;
 
-use super::{ConstantValue, JsValue, WellKnownFunctionKind, WellKnownObjectKind};
+use super::{
+    ConstantValue, JsValue, ModuleValue, WellKnownFunctionKind, WellKnownObjectKind,
+};
 
 pub fn replace_well_known(value: JsValue) -> (JsValue, bool) {
     match value {
@@ -14,6 +16,9 @@ pub fn replace_well_known(value: JsValue) -> (JsValue, bool) {
             ),
             true,
         ),
+        JsValue::Call(_, box JsValue::WellKnownObject(kind), args) => {
+            (well_known_object_call(kind, args), true)
+        }
         JsValue::Member(_, box JsValue::WellKnownObject(kind), box prop) => {
             (well_known_object_member(kind, prop), true)
         }
@@ -32,22 +37,45 @@ pub fn well_known_function_call(
     match kind {
         WellKnownFunctionKind::PathJoin => path_join(args),
         WellKnownFunctionKind::PathDirname => path_dirname(args),
-        WellKnownFunctionKind::Import => JsValue::Unknown(
-            Some(Arc::new(JsValue::call(
-                box JsValue::WellKnownFunction(kind),
-                args,
-            ))),
-            "import() is not supported",
-        ),
+        WellKnownFunctionKind::PathResolve => path_resolve(args),
+        WellKnownFunctionKind::PathNormalize => path_normalize(args),
+        WellKnownFunctionKind::PathBasename => path_basename(args),
+        WellKnownFunctionKind::PathExtname => path_extname(args),
+        WellKnownFunctionKind::Import => import(args),
         WellKnownFunctionKind::Require => require(args),
-        WellKnownFunctionKind::RequireResolve => JsValue::Unknown(
-            Some(Arc::new(JsValue::call(
-                box JsValue::WellKnownFunction(kind),
-                args,
-            ))),
-            "require.resolve() is not supported",
-        ),
+        WellKnownFunctionKind::RequireResolve => require_resolve(args),
         WellKnownFunctionKind::PathToFileUrl => path_to_file_url(args),
+        WellKnownFunctionKind::FsReadMethod(name) => {
+            // When the path is statically known the referenced file becomes an
+            // emitted asset (e.g. `readFileSync(join(__dirname, "template.html"))`
+            // pulls the template into the output). The method name is preserved so
+            // later passes can choose streaming (`createReadStream`) over inline
+            // embedding.
+            let referenced = args.get(0).and_then(|path| match path {
+                JsValue::Constant(ConstantValue::Str(s)) => Some(s.clone()),
+                JsValue::Url(url) if url.scheme() == "file" => url
+                    .to_file_path()
+                    .ok()
+                    .and_then(|p| p.to_str().map(|str| str.to_string().into())),
+                _ => None,
+            });
+            match referenced {
+                Some(file) => JsValue::FsReference(name, file),
+                None => JsValue::Unknown(
+                    Some(Arc::new(JsValue::call(
+                        box JsValue::WellKnownFunction(WellKnownFunctionKind::FsReadMethod(name)),
+                        args,
+                    ))),
+                    "fs read method with non-constant path is not supported",
+                ),
+            }
+        }
+        WellKnownFunctionKind::FileUrlToPath => file_url_to_path(args),
+        WellKnownFunctionKind::UrlConstructor => url_constructor(args),
+        WellKnownFunctionKind::NodeBindings => node_bindings(args),
+        WellKnownFunctionKind::NodeGypBuild => node_gyp_build(args),
+        WellKnownFunctionKind::NodePreGypFind => node_pre_gyp_find(args),
+        WellKnownFunctionKind::NodeExpressSet => express_set(args),
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::call(
                 box JsValue::WellKnownFunction(kind),
@@ -139,6 +167,136 @@ pub fn path_dirname(mut args: Vec<JsValue>) -> JsValue {
     )
 }
 
+pub fn path_resolve(args: Vec<JsValue>) -> JsValue {
+    // Process the arguments right-to-left, collecting segments until one is
+    // absolute (begins with `/`), exactly like Node's `path.resolve`.
+    let mut parts: Vec<JsValue> = Vec::new();
+    let mut absolute = false;
+    for arg in args.into_iter().rev() {
+        let is_absolute = arg.as_str().map(|s| s.starts_with('/')).unwrap_or(false);
+        parts.push(arg);
+        if is_absolute {
+            absolute = true;
+            break;
+        }
+    }
+    parts.reverse();
+    if !absolute {
+        // No absolute segment was found, so the result is rooted at the unknown
+        // cwd. Keep the resolvable suffix visible by leaving the leftmost
+        // boundary as an unknown prefix.
+        parts.insert(0, JsValue::alternatives(vec!["cwd-prefix".into(), "".into()]));
+    }
+    // Reuse path.join's segment folding to collapse `.`/`..`.
+    path_join(parts)
+}
+
+pub fn path_normalize(mut args: Vec<JsValue>) -> JsValue {
+    if let Some(arg) = args.iter_mut().next() {
+        if let Some(str) = arg.as_str() {
+            let is_absolute = str.starts_with('/');
+            // Fold segments with the same stack algorithm as path.join: push
+            // real segments, pop on `..`, and preserve leading `..` only when
+            // the stack is empty and the path is relative.
+            let mut leading: Vec<&str> = Vec::new();
+            let mut results: Vec<&str> = Vec::new();
+            for seg in str.split('/') {
+                match seg {
+                    "" | "." => {}
+                    ".." => {
+                        if results.pop().is_none() && !is_absolute {
+                            leading.push("..");
+                        }
+                    }
+                    _ => results.push(seg),
+                }
+            }
+            let body = leading
+                .into_iter()
+                .chain(results.into_iter())
+                .collect::<Vec<_>>()
+                .join("/");
+            let mut out = String::new();
+            if is_absolute {
+                out.push('/');
+            }
+            out.push_str(&body);
+            if out.is_empty() {
+                out.push('.');
+            }
+            return JsValue::Constant(ConstantValue::Str(out.into()));
+        }
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::PathNormalize),
+            args,
+        ))),
+        "path.normalize with unsupported arguments",
+    )
+}
+
+pub fn path_basename(args: Vec<JsValue>) -> JsValue {
+    if let Some(path) = args.get(0).and_then(str_or_last_concat) {
+        // Trailing slashes are ignored, like Node (`basename("/foo/") == "foo"`).
+        let trimmed = path.trim_end_matches('/');
+        let base = trimmed.rsplit('/').next().unwrap_or(trimmed);
+        let base = match args.get(1).and_then(|arg| arg.as_str()) {
+            // Node keeps the full segment when stripping the extension would
+            // leave nothing (`basename("index.js", "index.js") == "index.js"`).
+            Some(ext) => match base.strip_suffix(&*ext) {
+                Some(stripped) if !stripped.is_empty() => stripped,
+                _ => base,
+            },
+            None => base,
+        };
+        return JsValue::Constant(ConstantValue::Str(base.to_string().into()));
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::PathBasename),
+            args,
+        ))),
+        "path.basename with unsupported arguments",
+    )
+}
+
+pub fn path_extname(args: Vec<JsValue>) -> JsValue {
+    if let Some(path) = args.get(0).and_then(str_or_last_concat) {
+        let base = path.rsplit('/').next().unwrap_or(&path);
+        // A leading dot marks a dotfile, not an extension (Node returns "").
+        let ext = base
+            .rfind('.')
+            .filter(|&i| i > 0)
+            .map(|i| &base[i..])
+            .unwrap_or("");
+        return JsValue::Constant(ConstantValue::Str(ext.to_string().into()));
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::PathExtname),
+            args,
+        ))),
+        "path.extname with unsupported arguments",
+    )
+}
+
+/// Extracts a constant path string from a value, either directly or from the
+/// trailing constant segment of a `Concat`, for the path inspectors that only
+/// need the tail of the path.
+fn str_or_last_concat(value: &JsValue) -> Option<String> {
+    if let Some(str) = value.as_str() {
+        Some(str.to_string())
+    } else if let JsValue::Concat(_, items) = value {
+        items
+            .last()
+            .and_then(|last| last.as_str())
+            .map(|str| str.to_string())
+    } else {
+        None
+    }
+}
+
 pub fn require(args: Vec<JsValue>) -> JsValue {
     if args.len() == 1 {
         match &args[0] {
@@ -162,6 +320,86 @@ pub fn require(args: Vec<JsValue>) -> JsValue {
     }
 }
 
+pub fn import(args: Vec<JsValue>) -> JsValue {
+    if args.len() == 1 {
+        match &args[0] {
+            JsValue::Constant(ConstantValue::Str(s)) => JsValue::Module(s.clone()),
+            JsValue::Concat(_, parts) => {
+                // A partially-constant specifier such as `import(`./locales/${x}.js`)`
+                // still pins a resolvable prefix and extension. Turn the constant
+                // boundaries into a glob (dynamic segments become `*`) and tag it as
+                // a `Module` so the reference pass can enumerate the matching
+                // directory of candidate modules instead of dropping the import.
+                let mut has_constant = false;
+                let mut glob = String::new();
+                for part in parts {
+                    match part.as_str() {
+                        Some(str) => {
+                            has_constant = true;
+                            glob.push_str(str);
+                        }
+                        None => glob.push('*'),
+                    }
+                }
+                if has_constant {
+                    JsValue::Module(glob.into())
+                } else {
+                    JsValue::Unknown(
+                        Some(Arc::new(JsValue::call(
+                            box JsValue::WellKnownFunction(WellKnownFunctionKind::Import),
+                            args,
+                        ))),
+                        "import() with fully dynamic specifier is not supported",
+                    )
+                }
+            }
+            _ => JsValue::Unknown(
+                Some(Arc::new(JsValue::call(
+                    box JsValue::WellKnownFunction(WellKnownFunctionKind::Import),
+                    args,
+                ))),
+                "only constant argument is supported",
+            ),
+        }
+    } else {
+        JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::Import),
+                args,
+            ))),
+            "only a single argument is supported",
+        )
+    }
+}
+
+pub fn require_resolve(args: Vec<JsValue>) -> JsValue {
+    match args.split_first() {
+        // `require.resolve(id)` records the same dependency on `id` as `require`
+        // would, but its runtime value is the resolved filesystem path string
+        // rather than the module's exports, so it is tracked as `ModulePath`.
+        Some((JsValue::Constant(ConstantValue::Str(s)), rest)) if rest.is_empty() => {
+            JsValue::ModulePath(s.clone())
+        }
+        // An options object (`{ paths: [...] }`) changes the resolution base. We
+        // don't introspect it, so any form beyond the bare specifier falls back
+        // to Unknown.
+        Some((JsValue::Constant(ConstantValue::Str(_)), _)) => JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve),
+                args,
+            ))),
+            "require.resolve() with an options object is not supported",
+        ),
+        _ => JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve),
+                args,
+            ))),
+            "only constant argument is supported",
+        ),
+    }
+}
+
 pub fn path_to_file_url(args: Vec<JsValue>) -> JsValue {
     if args.len() == 1 {
         match &args[0] {
@@ -196,6 +434,74 @@ pub fn path_to_file_url(args: Vec<JsValue>) -> JsValue {
     }
 }
 
+pub fn file_url_to_path(args: Vec<JsValue>) -> JsValue {
+    if args.len() == 1 {
+        let url = match &args[0] {
+            JsValue::Url(url) => Some(url.clone()),
+            JsValue::Constant(ConstantValue::Str(s)) => Url::parse(s).ok(),
+            _ => None,
+        };
+        if let Some(url) = url {
+            if url.scheme() == "file" {
+                if let Some(path) = url.to_file_path().ok().and_then(|p| {
+                    p.to_str().map(|str| str.to_string())
+                }) {
+                    return JsValue::Constant(ConstantValue::Str(path.into()));
+                }
+            }
+        }
+        JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::FileUrlToPath),
+                args,
+            ))),
+            "url.fileURLToPath with a non-file URL is not supported",
+        )
+    } else {
+        JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::FileUrlToPath),
+                args,
+            ))),
+            "only a single argument is supported",
+        )
+    }
+}
+
+pub fn url_constructor(args: Vec<JsValue>) -> JsValue {
+    // Resolve the module-relative `new URL(relative, base)` pattern, where
+    // `base` is typically `import.meta.url` or `__dirname`, against the known
+    // base URL so downstream reference analysis can turn it into an asset
+    // dependency.
+    if args.len() == 2 {
+        if let (Some(relative), Some(base)) = (args[0].as_str(), url_base(&args[1])) {
+            if let Ok(joined) = base.join(relative) {
+                return JsValue::Url(joined);
+            }
+        }
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::UrlConstructor),
+            args,
+        ))),
+        "new URL() with unsupported arguments",
+    )
+}
+
+/// Interprets the second argument of `new URL(relative, base)` as a base URL,
+/// accepting an already-resolved `Url`, a `file://` string, or a bare directory
+/// path such as `__dirname`.
+fn url_base(value: &JsValue) -> Option<Url> {
+    match value {
+        JsValue::Url(url) => Some(url.clone()),
+        JsValue::Constant(ConstantValue::Str(s)) => {
+            Url::parse(s).ok().or_else(|| Url::from_directory_path(&**s).ok())
+        }
+        _ => None,
+    }
+}
+
 pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) -> JsValue {
     match (&kind, prop.as_str()) {
         (WellKnownFunctionKind::Require, Some("resolve")) => {
@@ -211,12 +517,34 @@ pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) ->
     }
 }
 
+/// Maps a required module specifier to the well-known object that models it, if
+/// any. This is the single source of truth shared by the analyzer and the
+/// reference pass so the two never disagree about which modules are special.
+///
+/// Besides the Node builtins it seeds a few packages that defeat naive static
+/// analysis by loading native addons or templating engines through indirection.
+pub fn module_value_to_well_known_object(module_value: ModuleValue) -> Option<WellKnownObjectKind> {
+    Some(match &*module_value.module {
+        "path" => WellKnownObjectKind::PathModule,
+        "fs" | "fs/promises" => WellKnownObjectKind::FsModule,
+        "url" => WellKnownObjectKind::UrlModule,
+        "child_process" => WellKnownObjectKind::ChildProcess,
+        "bindings" => WellKnownObjectKind::NodeBindingsModule,
+        "@mapbox/node-pre-gyp" => WellKnownObjectKind::NodePreGypModule,
+        "node-gyp-build" => WellKnownObjectKind::NodeGypBuildModule,
+        "express" => WellKnownObjectKind::NodeExpressModule,
+        _ => return None,
+    })
+}
+
 pub fn well_known_object_member(kind: WellKnownObjectKind, prop: JsValue) -> JsValue {
     match kind {
         WellKnownObjectKind::PathModule => path_module_member(prop),
         WellKnownObjectKind::FsModule => fs_module_member(prop),
         WellKnownObjectKind::UrlModule => url_module_member(prop),
         WellKnownObjectKind::ChildProcess => child_process_module_member(prop),
+        WellKnownObjectKind::NodePreGypModule => node_pre_gyp_module_member(prop),
+        WellKnownObjectKind::NodeExpressModule => express_module_member(prop),
         #[allow(unreachable_patterns)]
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
@@ -232,6 +560,16 @@ pub fn path_module_member(prop: JsValue) -> JsValue {
     match prop.as_str() {
         Some("join") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathJoin),
         Some("dirname") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathDirname),
+        Some("resolve") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathResolve),
+        Some("normalize") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathNormalize),
+        Some("basename") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathBasename),
+        Some("extname") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathExtname),
+        Some("sep") => JsValue::Constant(ConstantValue::Str("/".into())),
+        // `path.posix` shares our POSIX path model, so it resolves back to the
+        // path module. `path.win32` uses `\` separators that we intentionally do
+        // not model — returning the POSIX surface here would hand out a wrong
+        // `sep`, so leave it Unknown instead.
+        Some("posix") => JsValue::WellKnownObject(WellKnownObjectKind::PathModule),
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
                 box JsValue::WellKnownObject(WellKnownObjectKind::PathModule),
@@ -267,6 +605,7 @@ pub fn fs_module_member(prop: JsValue) -> JsValue {
 pub fn url_module_member(prop: JsValue) -> JsValue {
     match prop.as_str() {
         Some("pathToFileURL") => JsValue::WellKnownFunction(WellKnownFunctionKind::PathToFileUrl),
+        Some("fileURLToPath") => JsValue::WellKnownFunction(WellKnownFunctionKind::FileUrlToPath),
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
                 box JsValue::WellKnownObject(WellKnownObjectKind::UrlModule),
@@ -277,6 +616,128 @@ pub fn url_module_member(prop: JsValue) -> JsValue {
     }
 }
 
+/// Handles the packages whose default export is itself a function, such as
+/// `require("bindings")(...)` and `require("node-gyp-build")(__dirname)`.
+pub fn well_known_object_call(kind: WellKnownObjectKind, args: Vec<JsValue>) -> JsValue {
+    match kind {
+        WellKnownObjectKind::NodeBindingsModule => node_bindings(args),
+        WellKnownObjectKind::NodeGypBuildModule => node_gyp_build(args),
+        // `express()` creates the app whose `set` is modelled by the same kind.
+        WellKnownObjectKind::NodeExpressModule => {
+            JsValue::WellKnownObject(WellKnownObjectKind::NodeExpressModule)
+        }
+        _ => JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownObject(kind),
+                args,
+            ))),
+            "unsupported call on object kind",
+        ),
+    }
+}
+
+pub fn node_pre_gyp_module_member(prop: JsValue) -> JsValue {
+    match prop.as_str() {
+        Some("find") => JsValue::WellKnownFunction(WellKnownFunctionKind::NodePreGypFind),
+        _ => JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::NodePreGypModule),
+                box prop,
+            ))),
+            "unsupported property on @mapbox/node-pre-gyp module",
+        ),
+    }
+}
+
+pub fn express_module_member(prop: JsValue) -> JsValue {
+    match prop.as_str() {
+        // `app.set("view engine", "pug")` triggers a `require` of the templating
+        // package; model `set` so that require becomes traceable.
+        Some("set") => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeExpressSet),
+        _ => JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::NodeExpressModule),
+                box prop,
+            ))),
+            "unsupported property on express module",
+        ),
+    }
+}
+
+pub fn node_bindings(args: Vec<JsValue>) -> JsValue {
+    // `bindings("addon")` loads a native addon that conventionally lives under
+    // `build/Release`. The options-object form (`bindings({ bindings: "addon" })`)
+    // is not introspected and falls through to Unknown below.
+    if let Some(name) = args.get(0).and_then(|arg| arg.as_str()) {
+        let stem = name.strip_suffix(".node").unwrap_or(name);
+        return JsValue::Module(format!("./build/Release/{}.node", stem).into());
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::NodeBindings),
+            args,
+        ))),
+        "bindings() with non-constant argument is not supported",
+    )
+}
+
+pub fn node_gyp_build(args: Vec<JsValue>) -> JsValue {
+    // `node-gyp-build(__dirname)` returns the prebuilt addon located beneath the
+    // given package directory.
+    if let Some(dir) = args.get(0).and_then(|arg| arg.as_str()) {
+        let dir = dir.trim_end_matches('/');
+        return JsValue::Module(format!("{}/build/Release", dir).into());
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::NodeGypBuild),
+            args,
+        ))),
+        "node-gyp-build() with non-constant argument is not supported",
+    )
+}
+
+pub fn node_pre_gyp_find(args: Vec<JsValue>) -> JsValue {
+    // `.find(require.resolve("pkg/package.json"))` yields the prebuilt binary
+    // directory relative to the package's `package.json`.
+    let package_json = args.get(0).and_then(|arg| match arg {
+        JsValue::ModulePath(s) => Some(s.to_string()),
+        _ => arg.as_str().map(|str| str.to_string()),
+    });
+    if let Some(package_json) = package_json {
+        let dir = match package_json.rfind('/') {
+            Some(i) => &package_json[..i],
+            None => ".",
+        };
+        return JsValue::Module(format!("{}/build/Release", dir).into());
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::NodePreGypFind),
+            args,
+        ))),
+        "@mapbox/node-pre-gyp find() with non-constant argument is not supported",
+    )
+}
+
+pub fn express_set(args: Vec<JsValue>) -> JsValue {
+    // `app.set("view engine", "pug")` makes express `require` the templating
+    // package named by the second argument.
+    if let (Some("view engine"), Some(engine)) = (
+        args.get(0).and_then(|arg| arg.as_str()),
+        args.get(1).and_then(|arg| arg.as_str()),
+    ) {
+        return JsValue::Module(engine.to_string().into());
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::NodeExpressSet),
+            args,
+        ))),
+        "express set() with unsupported arguments",
+    )
+}
+
 pub fn child_process_module_member(prop: JsValue) -> JsValue {
     match prop.as_str() {
         Some("spawn") | Some("spawnSync") | Some("execFile") | Some("execFileSync") => {